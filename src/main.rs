@@ -1,9 +1,578 @@
-use std::{ffi::OsString, num::NonZeroU32, path::Path};
+use std::{ffi::OsString, fs::File, num::NonZeroU32, path::Path};
 
 use clap::Parser;
 
 use anyhow::{anyhow, Context, Result};
-use image::RgbaImage;
+use asefile::AnimationDirection;
+use clap::ValueEnum;
+use image::{
+    codecs::gif::{GifEncoder, Repeat},
+    imageops::FilterType,
+    Delay, Frame, ImageFormat, RgbaImage,
+};
+use serde::Serialize;
+
+/// How an image should be resized before being written out
+#[derive(Debug, Clone, Copy)]
+enum Resize {
+    /// Resize to an exact size, ignoring the original aspect ratio
+    Scale(u32, u32),
+    /// Resize to a target width, computing height to preserve aspect ratio
+    FitWidth(u32),
+    /// Resize to a target height, computing width to preserve aspect ratio
+    FitHeight(u32),
+    /// Resize to the largest size that fits inside the box, preserving aspect ratio
+    Fit(u32, u32),
+}
+
+impl Resize {
+    fn from_args(
+        scale: Option<(u32, u32)>,
+        fit_width: Option<u32>,
+        fit_height: Option<u32>,
+        fit: Option<(u32, u32)>,
+    ) -> Result<Option<Resize>> {
+        match (scale, fit_width, fit_height, fit) {
+            (None, None, None, None) => Ok(None),
+            (Some((w, h)), None, None, None) => Ok(Some(Resize::Scale(w, h))),
+            (None, Some(w), None, None) => Ok(Some(Resize::FitWidth(w))),
+            (None, None, Some(h), None) => Ok(Some(Resize::FitHeight(h))),
+            (None, None, None, Some((w, h))) => Ok(Some(Resize::Fit(w, h))),
+            _ => Err(anyhow!(
+                "--scale, --fit-width, --fit-height and --fit are mutually exclusive"
+            )),
+        }
+    }
+
+    fn target_size(&self, width: u32, height: u32) -> (u32, u32) {
+        match *self {
+            Resize::Scale(w, h) => (w, h),
+            Resize::FitWidth(w) => (w, ((height as u64 * w as u64 / width as u64) as u32).max(1)),
+            Resize::FitHeight(h) => (((width as u64 * h as u64 / height as u64) as u32).max(1), h),
+            Resize::Fit(w, h) => {
+                let scale = f64::min(w as f64 / width as f64, h as f64 / height as f64);
+                (
+                    ((width as f64 * scale).floor() as u32).max(1),
+                    ((height as f64 * scale).floor() as u32).max(1),
+                )
+            }
+        }
+    }
+}
+
+fn parse_dimensions(s: &str) -> Result<(u32, u32), String> {
+    let (w, h) = s
+        .split_once('x')
+        .ok_or_else(|| format!("expected WxH, got {s}"))?;
+    let w = w.parse().map_err(|_| format!("invalid width in {s}"))?;
+    let h = h.parse().map_err(|_| format!("invalid height in {s}"))?;
+    Ok((w, h))
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, Default)]
+enum Filter {
+    #[default]
+    Nearest,
+    Triangle,
+    CatmullRom,
+    Lanczos3,
+}
+
+impl From<Filter> for FilterType {
+    fn from(filter: Filter) -> Self {
+        match filter {
+            Filter::Nearest => FilterType::Nearest,
+            Filter::Triangle => FilterType::Triangle,
+            Filter::CatmullRom => FilterType::CatmullRom,
+            Filter::Lanczos3 => FilterType::Lanczos3,
+        }
+    }
+}
+
+fn resize_image(image: RgbaImage, resize: Option<Resize>, filter: Filter) -> RgbaImage {
+    match resize {
+        Some(resize) => {
+            let (width, height) = resize.target_size(image.width(), image.height());
+            image::imageops::resize(&image, width, height, filter.into())
+        }
+        None => image,
+    }
+}
+
+/// Output encoders the tool can write to, selectable with `--format` regardless of
+/// the output filename's extension
+#[derive(ValueEnum, Debug, Clone, Copy)]
+enum OutputFormat {
+    Png,
+    Webp,
+    Bmp,
+    Tga,
+    Qoi,
+    Tiff,
+    Gif,
+}
+
+impl OutputFormat {
+    fn extension(&self) -> &'static str {
+        match self {
+            OutputFormat::Png => "png",
+            OutputFormat::Webp => "webp",
+            OutputFormat::Bmp => "bmp",
+            OutputFormat::Tga => "tga",
+            OutputFormat::Qoi => "qoi",
+            OutputFormat::Tiff => "tiff",
+            OutputFormat::Gif => "gif",
+        }
+    }
+}
+
+impl From<OutputFormat> for ImageFormat {
+    fn from(format: OutputFormat) -> Self {
+        match format {
+            OutputFormat::Png => ImageFormat::Png,
+            OutputFormat::Webp => ImageFormat::WebP,
+            OutputFormat::Bmp => ImageFormat::Bmp,
+            OutputFormat::Tga => ImageFormat::Tga,
+            OutputFormat::Qoi => ImageFormat::Qoi,
+            OutputFormat::Tiff => ImageFormat::Tiff,
+            OutputFormat::Gif => ImageFormat::Gif,
+        }
+    }
+}
+
+fn save_image(image: &RgbaImage, path: &Path, format: Option<OutputFormat>) -> Result<()> {
+    match format {
+        Some(format) => image.save_with_format(path, format.into()),
+        None => image.save(path),
+    }
+    .with_context(|| format!("Cannot save image to {}", path.display()))
+}
+
+/// Expands a tag's `from_frame..=to_frame` range into playback order, honouring the
+/// tag's animation direction
+fn tag_frame_order(tag: &asefile::Tag) -> Vec<u32> {
+    let forward: Vec<u32> = (tag.from_frame()..=tag.to_frame()).collect();
+    match tag.animation_direction() {
+        AnimationDirection::Forward => forward,
+        AnimationDirection::Reverse => forward.into_iter().rev().collect(),
+        AnimationDirection::PingPong => {
+            let mut order = forward.clone();
+            order.extend(
+                forward
+                    .iter()
+                    .rev()
+                    .skip(1)
+                    .take(forward.len().saturating_sub(2)),
+            );
+            order
+        }
+    }
+}
+
+/// Returns the smallest rectangle containing every non-transparent pixel, as
+/// `(x, y, width, height)`, or `None` if the frame is fully transparent
+fn trim_bounds(image: &RgbaImage) -> Option<(u32, u32, u32, u32)> {
+    let (width, height) = image.dimensions();
+    let mut min_x = width;
+    let mut min_y = height;
+    let mut max_x = 0;
+    let mut max_y = 0;
+    let mut found = false;
+
+    for y in 0..height {
+        for x in 0..width {
+            if image.get_pixel(x, y)[3] != 0 {
+                found = true;
+                min_x = min_x.min(x);
+                min_y = min_y.min(y);
+                max_x = max_x.max(x);
+                max_y = max_y.max(y);
+            }
+        }
+    }
+
+    found.then(|| (min_x, min_y, max_x - min_x + 1, max_y - min_y + 1))
+}
+
+/// A skyline bin packer: the skyline is a list of horizontal segments
+/// `(x, y, width)` spanning the full sheet width, ordered left to right
+struct Skyline {
+    segments: Vec<(u32, u32, u32)>,
+    width: u32,
+}
+
+impl Skyline {
+    fn new(width: u32) -> Self {
+        Skyline {
+            segments: vec![(0, 0, width)],
+            width,
+        }
+    }
+
+    fn height(&self) -> u32 {
+        self.segments.iter().map(|&(_, y, _)| y).max().unwrap_or(0)
+    }
+
+    /// Finds the leftmost position where a `width`x`height` rect reaches the
+    /// lowest resulting top-y, places it there and returns its `(x, y)`
+    fn place(&mut self, width: u32, height: u32) -> Result<(u32, u32)> {
+        if width > self.width {
+            return Err(anyhow!(
+                "a frame is {width}px wide, which doesn't fit in a {}px wide atlas",
+                self.width
+            ));
+        }
+
+        let (start, x, y) = (0..self.segments.len())
+            .filter_map(|start| {
+                self.fit_at(start, width)
+                    .map(|y| (start, self.segments[start].0, y))
+            })
+            .min_by_key(|&(_, x, y)| (y, x))
+            .expect("a rect no wider than the sheet always fits somewhere");
+
+        self.insert(start, x, y, width, height);
+
+        Ok((x, y))
+    }
+
+    fn fit_at(&self, start: usize, width: u32) -> Option<u32> {
+        let x = self.segments[start].0;
+        if x + width > self.width {
+            return None;
+        }
+
+        let mut y = 0;
+        let mut covered = 0;
+        for &(seg_x, seg_y, seg_width) in &self.segments[start..] {
+            if seg_x >= x + width {
+                break;
+            }
+            y = y.max(seg_y);
+            covered += seg_width;
+        }
+
+        (covered >= width).then_some(y)
+    }
+
+    fn insert(&mut self, start: usize, x: u32, y: u32, width: u32, height: u32) {
+        let mut remaining = width;
+        while remaining > 0 && start < self.segments.len() {
+            let (seg_x, _, seg_width) = self.segments[start];
+            if seg_width <= remaining {
+                self.segments.remove(start);
+                remaining -= seg_width;
+            } else {
+                self.segments[start] = (
+                    seg_x + remaining,
+                    self.segments[start].1,
+                    seg_width - remaining,
+                );
+                remaining = 0;
+            }
+        }
+        self.segments.insert(start, (x, y + height, width));
+    }
+}
+
+/// One packed frame's entry in the `--atlas-json` sidecar
+#[derive(Serialize)]
+struct AtlasFrame {
+    tag: String,
+    frame: u32,
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+    trim_x: u32,
+    trim_y: u32,
+    source_width: u32,
+    source_height: u32,
+}
+
+#[derive(Serialize)]
+struct Atlas {
+    width: u32,
+    height: u32,
+    frames: Vec<AtlasFrame>,
+}
+
+/// Trims each frame's transparent border, then packs the trimmed rects into a
+/// skyline-packed sheet. The sheet width is fixed up front — the `--max-width`
+/// bound if given, otherwise the smallest power-of-two wide enough for the
+/// widest frame and the total trimmed area — and only the height grows to fit
+/// whatever the packer places.
+fn pack_atlas(
+    frames: Vec<(String, u32, RgbaImage)>,
+    max_width: Option<u32>,
+) -> Result<(RgbaImage, Atlas)> {
+    let trimmed: Vec<_> = frames
+        .into_iter()
+        .map(|(tag, frame, image)| {
+            let (source_width, source_height) = image.dimensions();
+            let (trim_x, trim_y, width, height) = trim_bounds(&image).unwrap_or((0, 0, 1, 1));
+            let trimmed_image =
+                image::imageops::crop_imm(&image, trim_x, trim_y, width, height).to_image();
+            (
+                tag,
+                frame,
+                trimmed_image,
+                trim_x,
+                trim_y,
+                source_width,
+                source_height,
+            )
+        })
+        .collect();
+
+    let width = match max_width {
+        Some(width) => width,
+        None => {
+            let total_area: u64 = trimmed
+                .iter()
+                .map(|(_, _, image, ..)| image.width() as u64 * image.height() as u64)
+                .sum();
+            let widest = trimmed
+                .iter()
+                .map(|(_, _, image, ..)| image.width())
+                .max()
+                .unwrap_or(1);
+
+            ((total_area as f64).sqrt().ceil() as u32)
+                .max(widest)
+                .next_power_of_two()
+        }
+    };
+
+    let mut skyline = Skyline::new(width);
+    let mut atlas_frames = Vec::with_capacity(trimmed.len());
+    for (tag, frame, image, trim_x, trim_y, source_width, source_height) in &trimmed {
+        let (x, y) = skyline.place(image.width(), image.height())?;
+        atlas_frames.push(AtlasFrame {
+            tag: tag.clone(),
+            frame: *frame,
+            x,
+            y,
+            width: image.width(),
+            height: image.height(),
+            trim_x: *trim_x,
+            trim_y: *trim_y,
+            source_width: *source_width,
+            source_height: *source_height,
+        });
+    }
+
+    let height = if max_width.is_some() {
+        skyline.height()
+    } else {
+        skyline.height().next_power_of_two()
+    };
+
+    let mut sheet = RgbaImage::new(width, height);
+    for (entry, (_, _, image, ..)) in atlas_frames.iter().zip(trimmed.iter()) {
+        image::imageops::replace(&mut sheet, image, entry.x, entry.y);
+    }
+
+    Ok((
+        sheet,
+        Atlas {
+            width,
+            height,
+            frames: atlas_frames,
+        },
+    ))
+}
+
+fn list_formats() -> Result<()> {
+    for format in OutputFormat::value_variants() {
+        if !ImageFormat::from(*format).writing_enabled() {
+            continue;
+        }
+
+        let name = format
+            .to_possible_value()
+            .expect("OutputFormat has no skipped variants")
+            .get_name()
+            .to_owned();
+        println!("{name}");
+    }
+    Ok(())
+}
+
+/// Inline image protocols a terminal emulator might understand
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TerminalProtocol {
+    Kitty,
+    ITerm2,
+    Sixel,
+    /// Unicode half-block fallback for terminals with no graphics protocol
+    Ansi,
+}
+
+fn detect_terminal_protocol() -> TerminalProtocol {
+    use std::env::var;
+
+    let term = var("TERM").unwrap_or_default();
+    let term_program = var("TERM_PROGRAM").unwrap_or_default();
+
+    if var("KITTY_WINDOW_ID").is_ok() || term.contains("kitty") {
+        TerminalProtocol::Kitty
+    } else if term_program == "iTerm.app" || var("LC_TERMINAL").as_deref() == Ok("iTerm2") {
+        TerminalProtocol::ITerm2
+    } else if term.contains("sixel")
+        || term_program == "WezTerm"
+        || var("COLORTERM").as_deref() == Ok("sixel")
+    {
+        TerminalProtocol::Sixel
+    } else {
+        TerminalProtocol::Ansi
+    }
+}
+
+fn render_kitty(image: &RgbaImage) -> Result<()> {
+    use base64::{engine::general_purpose::STANDARD, Engine};
+
+    let encoded = STANDARD.encode(image.as_raw());
+    let chunks: Vec<&[u8]> = encoded.as_bytes().chunks(4096).collect();
+
+    for (index, chunk) in chunks.iter().enumerate() {
+        let more = u8::from(index + 1 < chunks.len());
+        let chunk = std::str::from_utf8(chunk).expect("base64 output is always valid utf8");
+        if index == 0 {
+            print!(
+                "\x1b_Ga=T,f=32,s={},v={},m={more};{chunk}\x1b\\",
+                image.width(),
+                image.height()
+            );
+        } else {
+            print!("\x1b_Gm={more};{chunk}\x1b\\");
+        }
+    }
+    println!();
+
+    Ok(())
+}
+
+fn render_iterm2(image: &RgbaImage) -> Result<()> {
+    use base64::{engine::general_purpose::STANDARD, Engine};
+
+    let mut png_bytes = Vec::new();
+    image::DynamicImage::ImageRgba8(image.clone())
+        .write_to(&mut std::io::Cursor::new(&mut png_bytes), ImageFormat::Png)
+        .context("Cannot encode preview frame")?;
+
+    println!(
+        "\x1b]1337;File=inline=1;width={}px;height={}px;preserveAspectRatio=1:{}\x07",
+        image.width(),
+        image.height(),
+        STANDARD.encode(&png_bytes)
+    );
+
+    Ok(())
+}
+
+fn render_sixel(image: &RgbaImage) -> Result<()> {
+    const LEVELS: u32 = 6;
+
+    let palette: Vec<[u8; 3]> = (0..LEVELS)
+        .flat_map(|r| (0..LEVELS).flat_map(move |g| (0..LEVELS).map(move |b| (r, g, b))))
+        .map(|(r, g, b)| {
+            [
+                (r * 255 / (LEVELS - 1)) as u8,
+                (g * 255 / (LEVELS - 1)) as u8,
+                (b * 255 / (LEVELS - 1)) as u8,
+            ]
+        })
+        .collect();
+
+    let nearest_swatch = |pixel: image::Rgba<u8>| -> usize {
+        palette
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, swatch)| {
+                let dr = swatch[0] as i32 - pixel[0] as i32;
+                let dg = swatch[1] as i32 - pixel[1] as i32;
+                let db = swatch[2] as i32 - pixel[2] as i32;
+                dr * dr + dg * dg + db * db
+            })
+            .map(|(index, _)| index)
+            .expect("palette is never empty")
+    };
+
+    let mut sixel = String::from("\x1bP1;1;1q");
+    for (index, swatch) in palette.iter().enumerate() {
+        sixel.push_str(&format!(
+            "#{index};2;{};{};{}",
+            swatch[0] as u32 * 100 / 255,
+            swatch[1] as u32 * 100 / 255,
+            swatch[2] as u32 * 100 / 255,
+        ));
+    }
+
+    let (width, height) = image.dimensions();
+    for band_start in (0..height).step_by(6) {
+        for (palette_index, _) in palette.iter().enumerate() {
+            let mut band = String::with_capacity(width as usize);
+            let mut band_used = false;
+
+            for x in 0..width {
+                let mut bits = 0u8;
+                for bit in 0..6 {
+                    let Some(y) = band_start.checked_add(bit).filter(|&y| y < height) else {
+                        continue;
+                    };
+                    let pixel = *image.get_pixel(x, y);
+                    if pixel[3] != 0 && nearest_swatch(pixel) == palette_index {
+                        bits |= 1 << bit;
+                        band_used = true;
+                    }
+                }
+                band.push((63 + bits) as char);
+            }
+
+            if band_used {
+                sixel.push_str(&format!("#{palette_index}{band}$"));
+            }
+        }
+        sixel.push('-');
+    }
+    sixel.push_str("\x1b\\");
+
+    print!("{sixel}");
+
+    Ok(())
+}
+
+fn render_ansi(image: &RgbaImage) {
+    let (width, height) = image.dimensions();
+    for y in (0..height).step_by(2) {
+        for x in 0..width {
+            let top = *image.get_pixel(x, y);
+            let bottom = if y + 1 < height {
+                *image.get_pixel(x, y + 1)
+            } else {
+                image::Rgba([0, 0, 0, 0])
+            };
+            print!(
+                "\x1b[38;2;{};{};{}m\x1b[48;2;{};{};{}m\u{2580}",
+                top[0], top[1], top[2], bottom[0], bottom[1], bottom[2]
+            );
+        }
+        println!("\x1b[0m");
+    }
+}
+
+fn render_frame(image: &RgbaImage, protocol: TerminalProtocol) -> Result<()> {
+    match protocol {
+        TerminalProtocol::Kitty => render_kitty(image),
+        TerminalProtocol::ITerm2 => render_iterm2(image),
+        TerminalProtocol::Sixel => render_sixel(image),
+        TerminalProtocol::Ansi => {
+            render_ansi(image);
+            Ok(())
+        }
+    }
+}
 
 #[derive(Parser, Debug)]
 struct Args {
@@ -17,12 +586,30 @@ enum Action {
     Convert(Convert),
     Assemble(Assemble),
     Separate(Separate),
+    /// Exports a tag as an animated GIF, respecting each frame's duration
+    Animate(Animate),
+    /// Lists every output format this binary can write
+    ListFormats,
+    /// Renders a frame or tag inline in the terminal
+    Preview(Preview),
 }
 
 #[derive(Parser, Debug)]
 struct Convert {
     input_file: OsString,
     output_file: OsString,
+    #[arg(long, value_parser = parse_dimensions)]
+    scale: Option<(u32, u32)>,
+    #[arg(long)]
+    fit_width: Option<u32>,
+    #[arg(long)]
+    fit_height: Option<u32>,
+    #[arg(long, value_parser = parse_dimensions)]
+    fit: Option<(u32, u32)>,
+    #[arg(long, value_enum, default_value = "nearest")]
+    filter: Filter,
+    #[arg(long, value_enum)]
+    format: Option<OutputFormat>,
 }
 
 #[derive(Parser, Debug)]
@@ -35,6 +622,29 @@ struct Assemble {
     number_of_frames_from_each: Option<NonZeroU32>,
     #[arg(short, long)]
     columns: Option<NonZeroU32>,
+    #[arg(long, value_parser = parse_dimensions)]
+    scale: Option<(u32, u32)>,
+    #[arg(long)]
+    fit_width: Option<u32>,
+    #[arg(long)]
+    fit_height: Option<u32>,
+    #[arg(long, value_parser = parse_dimensions)]
+    fit: Option<(u32, u32)>,
+    #[arg(long, value_enum, default_value = "nearest")]
+    filter: Filter,
+    #[arg(long, value_enum)]
+    format: Option<OutputFormat>,
+    /// Tight-pack trimmed frames with a skyline bin packer instead of laying
+    /// them out on a fixed grid
+    #[arg(long)]
+    pack: bool,
+    /// Bound the packed sheet to this width instead of picking the smallest
+    /// power-of-two width
+    #[arg(long)]
+    max_width: Option<NonZeroU32>,
+    /// Write a JSON sidecar describing each packed frame's rect and trim offset
+    #[arg(long)]
+    atlas_json: Option<OsString>,
 }
 
 #[derive(Parser, Debug)]
@@ -43,6 +653,30 @@ struct Separate {
     output_directory: OsString,
     #[clap(short, long, value_parser, num_args = 1.., value_delimiter = ',')]
     tags: Vec<String>,
+    #[arg(long, value_enum)]
+    format: Option<OutputFormat>,
+}
+
+#[derive(Parser, Debug)]
+struct Animate {
+    input_file: OsString,
+    output_file: OsString,
+    #[arg(short, long)]
+    tag: String,
+    /// Number of times the animation repeats, omit for infinite looping
+    #[arg(short, long)]
+    loops: Option<u16>,
+}
+
+#[derive(Parser, Debug)]
+struct Preview {
+    input_file: OsString,
+    /// Loop this tag's animation instead of showing a single frame
+    #[arg(short, long)]
+    tag: Option<String>,
+    /// Frame index to show when `--tag` isn't given
+    #[arg(short, long)]
+    frame: Option<u32>,
 }
 
 impl Convert {
@@ -59,12 +693,11 @@ impl Convert {
             ));
         }
 
-        let input_image = input_file.frame(0).image();
+        let resize = Resize::from_args(self.scale, self.fit_width, self.fit_height, self.fit)?;
+        let input_image = resize_image(input_file.frame(0).image(), resize, self.filter);
 
         let output_path = Path::new(&self.output_file);
-        input_image
-            .save(output_path)
-            .with_context(|| format!("Cannot save image to {}", output_path.display()))?;
+        save_image(&input_image, output_path, self.format)?;
 
         Ok(())
     }
@@ -72,12 +705,83 @@ impl Convert {
 
 impl Assemble {
     fn assemble(&self) -> Result<()> {
+        if self.pack {
+            self.assemble_packed()
+        } else {
+            self.assemble_grid()
+        }
+    }
+
+    fn assemble_packed(&self) -> Result<()> {
         let input_path = Path::new(&self.input_file);
         let input_file = asefile::AsepriteFile::read_file(input_path)
             .with_context(|| format!("{} can't be loaded", input_path.display()))?;
 
-        let single_width = input_file.width();
-        let single_height = input_file.height();
+        let resize = Resize::from_args(self.scale, self.fit_width, self.fit_height, self.fit)?;
+
+        let number_of_frames_from_each = self
+            .number_of_frames_from_each
+            .map(|x| x.get())
+            .unwrap_or(1);
+
+        let mut frames = Vec::new();
+        for tag in self.tags.iter() {
+            let image_tag = input_file.tag_by_name(tag).with_context(|| {
+                format!("{tag} doesn't exist in image {}", input_path.display())
+            })?;
+
+            if (image_tag.to_frame() as i32 - image_tag.from_frame() as i32 + 1)
+                < (number_of_frames_from_each as i32)
+            {
+                return Err(anyhow!(
+                    "Tag {tag} in file {} doesn't contain enough frames, it has {} but we need {number_of_frames_from_each}",
+                    input_path.display(),
+                    image_tag.to_frame() as i32 - image_tag.from_frame() as i32 + 1,
+                ));
+            }
+
+            for i in 0..number_of_frames_from_each {
+                let frame_index = image_tag.from_frame() + i;
+                let frame = input_file.frame(frame_index);
+                let frame_image = resize_image(frame.image(), resize, self.filter);
+                frames.push((tag.clone(), frame_index, frame_image));
+            }
+        }
+
+        let (sheet, atlas) = pack_atlas(frames, self.max_width.map(|w| w.get()))?;
+
+        let output_path = Path::new(&self.output_file);
+        save_image(&sheet, output_path, self.format)?;
+
+        if let Some(atlas_json) = &self.atlas_json {
+            let atlas_json_path = Path::new(atlas_json);
+            let json =
+                serde_json::to_string_pretty(&atlas).context("Cannot serialise atlas metadata")?;
+            std::fs::write(atlas_json_path, json).with_context(|| {
+                format!(
+                    "Cannot write atlas metadata to {}",
+                    atlas_json_path.display()
+                )
+            })?;
+        }
+
+        Ok(())
+    }
+
+    fn assemble_grid(&self) -> Result<()> {
+        let input_path = Path::new(&self.input_file);
+        let input_file = asefile::AsepriteFile::read_file(input_path)
+            .with_context(|| format!("{} can't be loaded", input_path.display()))?;
+
+        let resize = Resize::from_args(self.scale, self.fit_width, self.fit_height, self.fit)?;
+
+        let (single_width, single_height) = match resize {
+            Some(resize) => {
+                resize.target_size(input_file.width() as u32, input_file.height() as u32)
+            }
+            None => (input_file.width() as u32, input_file.height() as u32),
+        };
+        let (single_width, single_height) = (single_width as usize, single_height as usize);
 
         let number_of_frames_from_each = self
             .number_of_frames_from_each
@@ -120,9 +824,10 @@ impl Assemble {
                 let y_pixel = y_image * single_height;
 
                 let frame = input_file.frame(image_tag.from_frame() + i as u32);
+                let frame_image = resize_image(frame.image(), resize, self.filter);
                 image::imageops::replace(
                     &mut output_image,
-                    &frame.image(),
+                    &frame_image,
                     x_pixel as u32,
                     y_pixel as u32,
                 );
@@ -130,9 +835,7 @@ impl Assemble {
         }
 
         let output_path = Path::new(&self.output_file);
-        output_image
-            .save(output_path)
-            .with_context(|| format!("Cannot save image to {}", output_path.display()))?;
+        save_image(&output_image, output_path, self.format)?;
 
         Ok(())
     }
@@ -153,16 +856,93 @@ impl Separate {
 
             let frame = input_file.frame(image_tag.from_frame());
 
-            let image_output_path = output_path.join(format!("{tag}.png"));
-            frame
-                .image()
-                .save(&image_output_path)
-                .with_context(|| format!("Cannot save image to {}", image_output_path.display()))?;
+            let extension = self.format.map(OutputFormat::extension).unwrap_or("png");
+            let image_output_path = output_path.join(format!("{tag}.{extension}"));
+            save_image(&frame.image(), &image_output_path, self.format)?;
         }
         Ok(())
     }
 }
 
+impl Animate {
+    fn animate(&self) -> Result<()> {
+        let input_path = Path::new(&self.input_file);
+        let input_file = asefile::AsepriteFile::read_file(input_path)
+            .with_context(|| format!("{} can't be loaded", input_path.display()))?;
+
+        let image_tag = input_file.tag_by_name(&self.tag).with_context(|| {
+            format!(
+                "{} doesn't exist in image {}",
+                self.tag,
+                input_path.display()
+            )
+        })?;
+
+        let frame_order = tag_frame_order(&image_tag);
+
+        let output_path = Path::new(&self.output_file);
+        let output_file = File::create(output_path)
+            .with_context(|| format!("Cannot create {}", output_path.display()))?;
+
+        let mut encoder = GifEncoder::new(output_file);
+        encoder.set_repeat(match self.loops {
+            Some(n) => Repeat::Finite(n),
+            None => Repeat::Infinite,
+        })?;
+
+        let frames = frame_order.into_iter().map(|index| {
+            let frame = input_file.frame(index);
+            let delay_centis = ((frame.duration() + 5) / 10).max(2);
+            Frame::from_parts(
+                frame.image(),
+                0,
+                0,
+                Delay::from_numer_denom_ms(delay_centis * 10, 1),
+            )
+        });
+
+        encoder
+            .encode_frames(frames)
+            .with_context(|| format!("Cannot write animation to {}", output_path.display()))?;
+
+        Ok(())
+    }
+}
+
+impl Preview {
+    fn preview(&self) -> Result<()> {
+        let input_path = Path::new(&self.input_file);
+        let input_file = asefile::AsepriteFile::read_file(input_path)
+            .with_context(|| format!("{} can't be loaded", input_path.display()))?;
+
+        let protocol = detect_terminal_protocol();
+
+        match &self.tag {
+            Some(tag) => {
+                let image_tag = input_file.tag_by_name(tag).with_context(|| {
+                    format!("{tag} doesn't exist in image {}", input_path.display())
+                })?;
+
+                let frame_order = tag_frame_order(&image_tag);
+
+                loop {
+                    for &index in &frame_order {
+                        let frame = input_file.frame(index);
+                        render_frame(&frame.image(), protocol)?;
+                        std::thread::sleep(std::time::Duration::from_millis(
+                            frame.duration() as u64
+                        ));
+                    }
+                }
+            }
+            None => {
+                let frame = input_file.frame(self.frame.unwrap_or(0));
+                render_frame(&frame.image(), protocol)
+            }
+        }
+    }
+}
+
 fn main() -> Result<()> {
     let cli = Args::parse();
 
@@ -170,6 +950,9 @@ fn main() -> Result<()> {
         Action::Convert(convert) => convert.convert()?,
         Action::Assemble(assemble) => assemble.assemble()?,
         Action::Separate(separate) => separate.separate()?,
+        Action::Animate(animate) => animate.animate()?,
+        Action::ListFormats => list_formats()?,
+        Action::Preview(preview) => preview.preview()?,
     }
 
     Ok(())